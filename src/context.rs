@@ -0,0 +1,153 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use core::any::TypeId;
+use core::fmt::{Debug, Display, Formatter};
+use core::result::Result as CoreResult;
+
+use crate::Error;
+#[cfg(not(feature = "std"))]
+use crate::StdError;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+/// An extension trait for attaching a human-readable message to an error.
+///
+/// This mirrors `anyhow::Context`: the message becomes part of the displayed
+/// cause chain, so a bare `No such file or directory` can be turned into
+/// `loading config: No such file or directory` while keeping the original
+/// span trace intact.
+pub trait Context<T> {
+    /// Wrap the error with an eagerly evaluated context message.
+    fn context<C>(self, context: C) -> crate::Result<T>
+    where
+        C: Display + Send + Sync + 'static;
+
+    /// Wrap the error with a lazily evaluated context message.
+    ///
+    /// The closure is only invoked in the error case, so it can perform work
+    /// that would be wasteful on the happy path.
+    fn with_context<C, F>(self, f: F) -> crate::Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C;
+}
+
+impl<T> Context<T> for crate::Result<T> {
+    fn context<C>(self, context: C) -> crate::Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        // Preserve the span trace and backtrace captured at the original site:
+        // wrapping an existing `Error` must not destroy its call history.
+        self.map_err(|error| error.wrap(context))
+    }
+
+    fn with_context<C, F>(self, f: F) -> crate::Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|error| error.wrap(f()))
+    }
+}
+
+impl<T, E> Context<T> for CoreResult<T, E>
+where
+    E: StdError + Send + Sync + 'static,
+{
+    fn context<C>(self, context: C) -> crate::Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        self.map_err(|source| {
+            let type_ids = vec![TypeId::of::<ContextError>(), TypeId::of::<E>()];
+            let source = Box::new(ContextError { context: Box::new(context), source: Box::new(source) });
+            Error::from_parts(source, type_ids)
+        })
+    }
+
+    fn with_context<C, F>(self, f: F) -> crate::Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|source| {
+            let type_ids = vec![TypeId::of::<ContextError>(), TypeId::of::<E>()];
+            let source = Box::new(ContextError { context: Box::new(f()), source: Box::new(source) });
+            Error::from_parts(source, type_ids)
+        })
+    }
+}
+
+impl Error {
+    /// Wrap the boxed source with a context message while keeping the report
+    /// handler that already captured the original span trace and backtrace.
+    ///
+    /// The context message is type-erased into `ContextError` immediately (a
+    /// single concrete type regardless of `C`), so wrapping only ever adds
+    /// one more `ContextError` entry to the front of `type_ids` instead of
+    /// overwriting it: `is`/`downcast*` still see the type that was wrapped,
+    /// the way anyhow's own context error special-cases the inner source.
+    fn wrap<C>(self, context: C) -> Self
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        let mut type_ids = self.type_ids;
+        type_ids.insert(0, TypeId::of::<ContextError>());
+        let source = Box::new(ContextError { context: Box::new(context), source: self.source });
+        Self {
+            source,
+            type_ids,
+            handler: self.handler,
+        }
+    }
+}
+
+/// An error that prepends a context message to its source.
+///
+/// The previous error is retained as [`StdError::source`], which is the link
+/// [`Error::downcast_ref`] and [`Error::downcast_mut`] follow to reach a
+/// wrapped error. The [`Error`] `Debug` report does not walk `source()`: it
+/// prints this type's `Display` form, `context: source`, so every context
+/// message and the innermost error's own message appear ahead of the span
+/// trace, while causes that the innermost error exposes only through its own
+/// `source()` are not printed. The context message is boxed as `dyn Display` rather than kept
+/// generic so that `ContextError` is a single concrete type no matter how
+/// many times (or with what message types) an `Error` has been wrapped; that
+/// lets [`Error::downcast`] peel layers off by their recorded `TypeId`
+/// without needing to name the message type.
+struct ContextError {
+    context: Box<dyn Display + Send + Sync + 'static>,
+    source: Box<dyn StdError + Send + Sync + 'static>,
+}
+
+impl Display for ContextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}: {}", self.context, self.source)
+    }
+}
+
+impl Debug for ContextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl StdError for ContextError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Peel one `ContextError` layer off a boxed source, moving out the wrapped
+/// source and dropping the context message.
+///
+/// # Safety
+///
+/// `source`'s concrete type must be `ContextError` — callers check this via
+/// [`Error`]'s `type_ids` chain before calling.
+pub(crate) unsafe fn peel(source: Box<dyn StdError + Send + Sync + 'static>) -> Box<dyn StdError + Send + Sync + 'static> {
+    let raw = Box::into_raw(source) as *mut ContextError;
+    let boxed = unsafe { Box::from_raw(raw) };
+    boxed.source
+}
@@ -37,6 +37,20 @@
 //! * ✅ Can be constructed from existing error types, just like [`anyhow::Error`]
 //! * ✅ Captures the current tracing span, just like [`tracing_error::TracedError<E>`]
 //!
+//! Attach a human-readable message to any error with [`Context::context`] (eager) or [`Context::with_context`] (lazy), the way `anyhow`'s `.context(...)` works. The message is prepended to the displayed cause chain while the original source — and its span trace — are kept underneath.
+//!
+//! Recover the concrete source error with [`Error::downcast`], [`Error::downcast_ref`], or [`Error::downcast_mut`], and test for a type with [`Error::is`]. This includes the `&str`/`String` message produced by [`helpful!`]/[`bail!`]; on a type mismatch [`Error::downcast`] hands back the original `Error` unchanged, span trace and backtrace included.
+//!
+//! Customize how reports are rendered by implementing [`ReportHandler`] and installing it once at startup with [`set_hook`] — to emit machine-readable output, drop the backtrace in tests, or apply bespoke styling.
+//!
+//! Construct ad-hoc errors and return early with the [`helpful!`], [`bail!`], and [`ensure!`] macros, mirroring `anyhow`'s `anyhow!`/`bail!`/`ensure!`. Each still captures the current span trace.
+//!
+//! Enrich an error with actionable help text — notes, warnings, and suggestions — through the [`Section`] trait, rendered after the source message and before the call history.
+//!
+//! Enable the `color` cargo feature to render the `Debug` report with ANSI styling: a bold-red source message, a colorized span trace, and a per-frame backtrace that highlights user code while dimming std/runtime frames. It honors `NO_COLOR`/`CLICOLOR` and only colorizes when stderr is a TTY.
+//!
+//! Enable the `stable-backtrace` cargo feature to capture backtraces via the [`backtrace`](https://docs.rs/backtrace) crate instead of `std::backtrace`, giving reliable, resolved frames on stable toolchains. It respects `RUST_BACKTRACE`/`RUST_LIB_BACKTRACE`; the std path remains the default.
+//!
 //! # Benefits
 //!
 //! * Provides a detailed span trace to the user (which makes it easier to diagnose the root cause of the error).
@@ -144,17 +158,16 @@ extern crate alloc;
 extern crate core;
 
 use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::TypeId;
 use core::fmt::{Debug, Display, Formatter};
 use core::result::Result as StdResult;
 #[cfg(feature = "std")]
-use std::backtrace::{Backtrace, BacktraceStatus};
-#[cfg(feature = "std")]
 use std::error::Error as StdError;
 #[cfg(feature = "std")]
 use std::process::{ExitCode, Termination};
 
-use tracing_error::SpanTrace;
-
 #[cfg(not(feature = "std"))]
 pub trait StdError: Debug + Display {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
@@ -162,28 +175,142 @@ pub trait StdError: Debug + Display {
     }
 }
 
+mod context;
+mod report;
+mod section;
 mod wrapper;
 
-pub use wrapper::*;
+pub use context::*;
+pub use report::*;
+pub use section::*;
+
+use report::make_handler;
+use wrapper::MessageError;
 
 /// The main `Error` type that provides additional information via `SpanTrace`.
 ///
 /// This type doesn't implement the `Error` trait because it conflicts with a blanket `From<E>` implementation (which allows converting any error to this type). This is the same reason why `anyhow::Error` doesn't implement `Error`.
 pub struct Error {
-    pub source: Box<dyn StdError + Send + Sync + 'static>,
-    pub span_trace: SpanTrace,
-    #[cfg(feature = "std")]
-    pub backtrace: Backtrace,
+    /// The boxed source error.
+    ///
+    /// Private on purpose: the safety of the `downcast*` casts relies on
+    /// `type_ids` describing the concrete types nested behind this box, so
+    /// the box must not be reassignable from the outside. Use
+    /// [`Error::source`] for read access.
+    source: Box<dyn StdError + Send + Sync + 'static>,
+    /// The `TypeId` of the concrete type at each link of the source chain,
+    /// outermost first: `type_ids[0]` describes `source` itself,
+    /// `type_ids[1]` describes `source.source()`, and so on.
+    ///
+    /// A plain `dyn StdError` trait object is not `Any`-downcastable, so we
+    /// remember the chain of original types here and compare against it
+    /// before recovering a concrete error in [`Error::downcast`] and friends.
+    /// [`Context::context`](crate::Context::context) prepends one entry per
+    /// wrap instead of replacing the chain, so downcasting still reaches the
+    /// error underneath any amount of added context.
+    type_ids: Vec<TypeId>,
+    /// The report handler that captured this error's context and renders it.
+    handler: Box<dyn ReportHandler>,
 }
 
 impl Error {
     pub fn new<E: StdError + Send + Sync + 'static>(source: E) -> Self {
+        Self::from_parts(Box::new(source), vec![TypeId::of::<E>()])
+    }
+
+    /// Construct from an already-boxed source and the chain of `TypeId`s
+    /// describing each link, outermost first. `type_ids[0]` must describe the
+    /// value actually behind `source`, `type_ids[1]` the value reached via one
+    /// `StdError::source()` call, and so on, otherwise the `downcast*` methods
+    /// are unsound.
+    pub(crate) fn from_parts(source: Box<dyn StdError + Send + Sync + 'static>, type_ids: Vec<TypeId>) -> Self {
+        let handler = make_handler(source.as_ref());
         Self {
-            source: Box::new(source),
-            span_trace: SpanTrace::capture(),
-            #[cfg(feature = "std")]
-            backtrace: Backtrace::capture(),
+            source,
+            type_ids,
+            handler,
+        }
+    }
+
+    /// Access the boxed source error.
+    pub fn source(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        self.source.as_ref()
+    }
+
+    /// Returns `true` if `E` appears anywhere in the source chain (the error
+    /// itself, or a cause underneath any amount of attached context).
+    pub fn is<E>(&self) -> bool
+    where
+        E: Display + Debug + Send + Sync + 'static,
+    {
+        self.type_ids.contains(&TypeId::of::<E>())
+    }
+
+    /// Find how many `StdError::source()` hops separate `self.source` from the
+    /// first link whose recorded type is `E`.
+    fn depth_of<E: 'static>(&self) -> Option<usize> {
+        self.type_ids.iter().position(|type_id| *type_id == TypeId::of::<E>())
+    }
+
+    /// Downcast a shared reference to a concrete type anywhere in the source
+    /// chain.
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: Display + Debug + Send + Sync + 'static,
+    {
+        let depth = self.depth_of::<E>()?;
+        let mut current: &(dyn StdError + 'static) = widen(self.source.as_ref());
+        for _ in 0..depth {
+            current = current.source().expect("type_ids chain deeper than the actual source chain");
         }
+        // Safe: `type_ids[depth]` was recorded as the concrete type of this
+        // link when the chain was built.
+        Some(unsafe { &*(current as *const (dyn StdError + 'static) as *const E) })
+    }
+
+    /// Downcast a mutable reference to a concrete type anywhere in the source
+    /// chain.
+    pub fn downcast_mut<E>(&mut self) -> Option<&mut E>
+    where
+        E: Display + Debug + Send + Sync + 'static,
+    {
+        let depth = self.depth_of::<E>()?;
+        // `StdError` only exposes a shared `source()`, so walk the chain by
+        // shared reference and cast to a raw pointer; the `&mut self` we hold
+        // guarantees no other access to this chain exists meanwhile.
+        let mut current: *const (dyn StdError + 'static) = widen(self.source.as_ref());
+        for _ in 0..depth {
+            current = unsafe { &*current }.source().expect("type_ids chain deeper than the actual source chain");
+        }
+        // Safe: `type_ids[depth]` was recorded as the concrete type of this
+        // link when the chain was built.
+        Some(unsafe { &mut *(current as *mut E) })
+    }
+
+    /// Recover a concrete type anywhere in the source chain by value.
+    ///
+    /// On a type mismatch the original `Error` is returned unchanged so the
+    /// caller can keep propagating it, span trace and backtrace included.
+    /// Any context layers above the match are discarded along the way.
+    pub fn downcast<E>(self) -> StdResult<E, Self>
+    where
+        E: Display + Debug + Send + Sync + 'static,
+    {
+        let Some(depth) = self.depth_of::<E>() else {
+            return Err(self);
+        };
+        let Error { mut source, .. } = self;
+        for _ in 0..depth {
+            // Safe: `type_ids[0..depth]` are context layers by construction
+            // (only the `Context` impls add entries beyond the first, one
+            // `ContextError` per entry), so `source`'s concrete type really
+            // is `ContextError`.
+            source = unsafe { context::peel(source) };
+        }
+        // Safe: `type_ids[depth]` was recorded as the concrete type of this
+        // link when the chain was built.
+        let raw = Box::into_raw(source) as *mut E;
+        Ok(*unsafe { Box::from_raw(raw) })
     }
 
     #[cold]
@@ -192,14 +319,29 @@ impl Error {
     where
         M: Display + Debug + Send + Sync + 'static,
     {
-        Self::new(MessageError(message))
+        // Record the `TypeId` of `M` rather than of the `MessageError<M>`
+        // wrapper, so that errors built via `helpful!`/`bail!`/`ensure!` can be
+        // recovered with `downcast::<M>()`, the way anyhow's ad-hoc path works.
+        // `MessageError<M>` is `#[repr(transparent)]`, so the downcast cast to
+        // `M` is sound.
+        Self::from_parts(Box::new(MessageError(message)), vec![TypeId::of::<M>()])
     }
 }
 
+/// Drop the `Send + Sync` bounds from a source reference so it can be walked
+/// via the plain `StdError::source()` chain (a coercion, not a cast — the
+/// data pointer is unchanged).
+fn widen<'a>(source: &'a (dyn StdError + Send + Sync + 'static)) -> &'a (dyn StdError + 'static) {
+    source
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         if f.alternate() {
-            Display::fmt(self.source.as_ref(), f)
+            // Route through the installed handler (default: the source's own
+            // `Display`) instead of hardcoding it, so a custom `ReportHandler`
+            // can also control the one-line form.
+            self.handler.display(self.source.as_ref(), f)
         } else {
             f.pad("Error: ")?;
             Debug::fmt(self, f)
@@ -209,21 +351,7 @@ impl Display for Error {
 
 impl Debug for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        if f.alternate() {
-            Debug::fmt(self.source.as_ref(), f)
-        } else {
-            Display::fmt(self.source.as_ref(), f)?;
-            f.pad("\n\n")?;
-            f.pad("Call history (recent first):\n")?;
-            Display::fmt(&self.span_trace, f)?;
-            #[cfg(feature = "std")]
-            if let BacktraceStatus::Captured = self.backtrace.status() {
-                f.pad("\n\n")?;
-                f.pad("Backtrace:\n")?;
-                Display::fmt(&self.backtrace, f)?;
-            }
-            Ok(())
-        }
+        self.handler.debug(self.source.as_ref(), f)
     }
 }
 
@@ -295,6 +423,38 @@ macro_rules! helpful {
     };
 }
 
+/// Return early with an error, like `anyhow::bail!`.
+///
+/// Accepts the same literal and format-args forms as [`helpful!`], so the
+/// returned error still captures the current span trace and backtrace.
+#[macro_export]
+macro_rules! bail {
+    ($msg:literal $(,)?) => {
+        return $crate::__private::Err($crate::helpful!($msg))
+    };
+    ($fmt:expr, $($arg:tt)*) => {
+        return $crate::__private::Err($crate::helpful!($fmt, $($arg)*))
+    };
+}
+
+/// Return early with an error if a condition is not satisfied, like
+/// `anyhow::ensure!`.
+///
+/// Accepts the same literal and format-args forms as [`helpful!`].
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr, $msg:literal $(,)?) => {
+        if $crate::__private::not($cond) {
+            return $crate::__private::Err($crate::helpful!($msg));
+        }
+    };
+    ($cond:expr, $fmt:expr, $($arg:tt)*) => {
+        if $crate::__private::not($cond) {
+            return $crate::__private::Err($crate::helpful!($fmt, $($arg)*));
+        }
+    };
+}
+
 // Not public API. Referenced by macro-generated code.
 // Copied from `anyhow` with omissions
 #[doc(hidden)]
@@ -365,3 +525,314 @@ pub mod __private {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::String;
+
+    #[test]
+    fn downcast_recovers_str_message() {
+        let error = Error::msg("boom");
+        assert!(error.is::<&str>());
+        assert!(!error.is::<String>());
+        assert_eq!(*error.downcast_ref::<&str>().unwrap(), "boom");
+        assert_eq!(error.downcast::<&str>().unwrap(), "boom");
+    }
+
+    #[test]
+    fn downcast_recovers_string_message() {
+        let error = Error::msg(String::from("boom"));
+        assert!(error.is::<String>());
+        assert_eq!(error.downcast::<String>().unwrap(), "boom");
+    }
+
+    #[test]
+    fn downcast_mismatch_returns_original_error() {
+        let error = Error::msg("boom");
+        // The wrong target type hands the error back untouched, message intact.
+        let error = error.downcast::<String>().unwrap_err();
+        assert_eq!(format!("{error:#}"), "boom");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_recovers_source_error() {
+        use std::io;
+        let error = Error::new(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert!(error.is::<io::Error>());
+        let recovered = error.downcast::<io::Error>().unwrap();
+        assert_eq!(recovered.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_recovers_source_through_context() {
+        use crate::Context;
+        use std::io;
+
+        let result: crate::Result<()> = Err(io::Error::new(io::ErrorKind::NotFound, "missing")).context("loading config");
+        let error = result.unwrap_err();
+        assert!(error.is::<io::Error>());
+        assert_eq!(format!("{error:#}"), "loading config: missing");
+        let recovered = error.downcast::<io::Error>().unwrap();
+        assert_eq!(recovered.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_recovers_source_through_nested_context() {
+        use crate::Context;
+        use std::io;
+
+        let result: crate::Result<()> = Err(io::Error::new(io::ErrorKind::NotFound, "missing")).context("loading config").context("starting up");
+        let error = result.unwrap_err();
+        assert!(error.is::<io::Error>());
+        assert_eq!(format!("{error:#}"), "starting up: loading config: missing");
+        let recovered = error.downcast::<io::Error>().unwrap();
+        assert_eq!(recovered.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn downcast_through_context_mismatch_returns_original_error() {
+        use crate::Context;
+
+        let result: crate::Result<()> = Err(helpful!("boom")).context("loading config");
+        let error = result.unwrap_err();
+        let error = error.downcast::<i32>().unwrap_err();
+        assert_eq!(format!("{error:#}"), "loading config: boom");
+    }
+
+    #[test]
+    fn downcast_ref_recovers_str_message() {
+        let error = Error::msg("boom");
+        assert_eq!(*error.downcast_ref::<&str>().unwrap(), "boom");
+    }
+
+    #[test]
+    fn downcast_ref_mismatch_returns_none() {
+        let error = Error::msg("boom");
+        assert!(error.downcast_ref::<String>().is_none());
+        // The error is left intact for further use.
+        assert_eq!(*error.downcast_ref::<&str>().unwrap(), "boom");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_ref_recovers_source_error() {
+        use std::io;
+        let error = Error::new(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        let recovered = error.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(recovered.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_ref_recovers_source_through_context() {
+        use crate::Context;
+        use std::io;
+
+        let result: crate::Result<()> = Err(io::Error::new(io::ErrorKind::NotFound, "missing")).context("loading config");
+        let error = result.unwrap_err();
+        let recovered = error.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(recovered.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_ref_recovers_source_through_nested_context() {
+        use crate::Context;
+        use std::io;
+
+        let result: crate::Result<()> = Err(io::Error::new(io::ErrorKind::NotFound, "missing")).context("loading config").context("starting up");
+        let error = result.unwrap_err();
+        let recovered = error.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(recovered.kind(), io::ErrorKind::NotFound);
+    }
+
+    /// A source error with a mutable field, so `downcast_mut` tests can assert
+    /// that a mutation through the returned reference is actually observed.
+    #[cfg(feature = "std")]
+    #[derive(Debug)]
+    struct CountingError {
+        count: i32,
+    }
+
+    #[cfg(feature = "std")]
+    impl Display for CountingError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            write!(f, "count is {}", self.count)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl StdError for CountingError {}
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_mut_mismatch_returns_none() {
+        let mut error = Error::new(CountingError { count: 1 });
+        assert!(error.downcast_mut::<String>().is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_mut_mutates_source_error() {
+        let mut error = Error::new(CountingError { count: 1 });
+        error.downcast_mut::<CountingError>().unwrap().count += 1;
+        assert_eq!(error.downcast_ref::<CountingError>().unwrap().count, 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_mut_mutates_source_through_context() {
+        use crate::Context;
+
+        let result: crate::Result<()> = Err(CountingError { count: 1 }).context("loading config");
+        let mut error = result.unwrap_err();
+        error.downcast_mut::<CountingError>().unwrap().count += 1;
+        assert_eq!(error.downcast_ref::<CountingError>().unwrap().count, 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_mut_mutates_source_through_nested_context() {
+        use crate::Context;
+
+        let result: crate::Result<()> = Err(CountingError { count: 1 }).context("loading config").context("starting up");
+        let mut error = result.unwrap_err();
+        error.downcast_mut::<CountingError>().unwrap().count += 1;
+        assert_eq!(error.downcast_ref::<CountingError>().unwrap().count, 2);
+    }
+
+    #[test]
+    fn with_context_runs_the_closure_only_on_the_error_path() {
+        use core::cell::Cell;
+        use crate::Context;
+
+        let calls = Cell::new(0);
+        let to_context = || {
+            calls.set(calls.get() + 1);
+            "loading config"
+        };
+
+        let ok: crate::Result<()> = StdResult::<(), Error>::Ok(()).with_context(to_context);
+        assert!(ok.is_ok());
+        assert_eq!(calls.get(), 0, "the closure must not run when there's no error to contextualize");
+
+        let err: crate::Result<()> = Err(helpful!("boom")).with_context(to_context);
+        assert_eq!(format!("{:#}", err.unwrap_err()), "loading config: boom");
+        assert_eq!(calls.get(), 1, "the closure must run exactly once on the error path");
+    }
+
+    fn bail_literal() -> crate::Result<()> {
+        bail!("boom");
+    }
+
+    fn bail_format(name: &str) -> crate::Result<()> {
+        bail!("boom: {name}");
+    }
+
+    fn ensure_literal(cond: bool) -> crate::Result<()> {
+        ensure!(cond, "boom");
+        Ok(())
+    }
+
+    fn ensure_format(cond: bool, name: &str) -> crate::Result<()> {
+        ensure!(cond, "boom: {name}");
+        Ok(())
+    }
+
+    #[test]
+    fn bail_returns_the_literal_message() {
+        let error = bail_literal().unwrap_err();
+        assert_eq!(format!("{error:#}"), "boom");
+    }
+
+    #[test]
+    fn bail_returns_the_formatted_message() {
+        let error = bail_format("config").unwrap_err();
+        assert_eq!(format!("{error:#}"), "boom: config");
+    }
+
+    #[test]
+    fn ensure_passes_through_when_condition_holds() {
+        assert!(ensure_literal(true).is_ok());
+    }
+
+    #[test]
+    fn ensure_returns_the_literal_message_when_condition_fails() {
+        let error = ensure_literal(false).unwrap_err();
+        assert_eq!(format!("{error:#}"), "boom");
+    }
+
+    #[test]
+    fn ensure_returns_the_formatted_message_when_condition_fails() {
+        let error = ensure_format(false, "config").unwrap_err();
+        assert_eq!(format!("{error:#}"), "boom: config");
+    }
+
+    #[test]
+    fn section_renders_notes_warnings_and_suggestions() {
+        use crate::Section;
+
+        let error = Error::msg("boom").suggestion("pass --config <path>").note("looked in ./config.json").warning("defaults may be stale");
+        let report = format!("{error:?}");
+        assert!(report.contains("Suggestion"));
+        assert!(report.contains("pass --config <path>"));
+        assert!(report.contains("Note"));
+        assert!(report.contains("looked in ./config.json"));
+        assert!(report.contains("Warning"));
+        assert!(report.contains("defaults may be stale"));
+        // Suggestions render first, then notes, then warnings.
+        let suggestion_pos = report.find("pass --config <path>").unwrap();
+        let note_pos = report.find("looked in ./config.json").unwrap();
+        let warning_pos = report.find("defaults may be stale").unwrap();
+        assert!(suggestion_pos < note_pos);
+        assert!(note_pos < warning_pos);
+    }
+
+    #[cfg(feature = "color")]
+    #[test]
+    fn color_feature_stays_plain_when_not_a_tty() {
+        // Assumes stderr is not a terminal (as in CI or with redirected
+        // output): libtest's capture only intercepts the print macros, not the
+        // stderr descriptor that `is_terminal()` checks. Under that condition
+        // the report must come out with no ANSI escapes even with `color` on.
+        let error = Error::msg("boom");
+        let report = format!("{error:?}");
+        assert!(!report.contains('\u{1b}'), "report should have no ANSI escapes when stderr isn't a TTY: {report:?}");
+    }
+
+    #[cfg(feature = "stable-backtrace")]
+    #[test]
+    fn stable_backtrace_report_round_trips() {
+        // Exercises capture + render via the `backtrace` crate instead of
+        // `std::backtrace`; should render the same call-history section
+        // regardless of whether `RUST_BACKTRACE` happens to be set.
+        let error = Error::msg("boom");
+        let report = format!("{error:?}");
+        assert!(report.contains("Call history (recent first):"));
+    }
+
+    #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+    #[test]
+    fn runtime_frame_heuristic_dims_crate_internals_not_user_code() {
+        use crate::report::is_runtime_frame;
+
+        // The crate's own capture call chain is bookkeeping, not the user's
+        // call site, so it should be dimmed like std/core frames.
+        assert!(is_runtime_frame("helpful::Error::new"));
+        assert!(is_runtime_frame("helpful::report::make_handler"));
+        assert!(is_runtime_frame("<helpful::Error as core::convert::From<E>>::from"));
+        assert!(is_runtime_frame("std::rt::lang_start"));
+
+        // The runtime entry point is dimmed, but a user function that merely
+        // starts with "main" is not swept up by a loose prefix match.
+        assert!(is_runtime_frame("main"));
+        assert!(is_runtime_frame("main::{{closure}}"));
+        assert!(!is_runtime_frame("maintenance_check"));
+        assert!(!is_runtime_frame("my_crate::config::load"));
+    }
+}
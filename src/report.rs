@@ -0,0 +1,313 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter};
+
+#[cfg(all(feature = "std", not(any(feature = "stable-backtrace", feature = "color"))))]
+use std::backtrace::{Backtrace, BacktraceStatus};
+#[cfg(not(feature = "std"))]
+use crate::StdError;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+use tracing_error::SpanTrace;
+
+/// A pluggable strategy for formatting an [`Error`](crate::Error) report.
+///
+/// Following eyre's `EyreHandler`, the handler owns whatever context was
+/// captured when the error was created (the span trace and backtrace for the
+/// [`DefaultHandler`]) and is responsible for rendering it. Downstream crates
+/// can install their own handler via [`set_hook`] to emit machine-readable
+/// reports, drop the backtrace in tests, or colorize the output.
+pub trait ReportHandler: Send + Sync + 'static {
+    /// Render the full `Debug` report for `error`.
+    fn debug(&self, error: &(dyn StdError + 'static), f: &mut Formatter<'_>) -> core::fmt::Result;
+
+    /// Render the `Display` form. Defaults to the error's own `Display`.
+    fn display(&self, error: &(dyn StdError + 'static), f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(error, f)
+    }
+
+    /// Mutable access to the handler's help annotations, if it keeps any.
+    ///
+    /// Used by the [`Section`](crate::Section) trait to attach notes,
+    /// warnings, and suggestions. Handlers that don't render help return
+    /// `None` and the annotation is silently dropped.
+    fn help_mut(&mut self) -> Option<&mut Vec<HelpEntry>> {
+        None
+    }
+}
+
+/// A typed help annotation attached to an error via the
+/// [`Section`](crate::Section) trait.
+pub enum HelpEntry {
+    Note(Box<dyn Display + Send + Sync + 'static>),
+    Warning(Box<dyn Display + Send + Sync + 'static>),
+    Suggestion(Box<dyn Display + Send + Sync + 'static>),
+}
+
+/// The built-in handler, reproducing the default "Call history / Backtrace"
+/// report.
+pub struct DefaultHandler {
+    span_trace: SpanTrace,
+    // When `color` or `stable-backtrace` is on we capture via the `backtrace`
+    // crate, whose frames can be iterated for per-frame colorization (the std
+    // backtrace exposes no such API). Otherwise keep the std backtrace so the
+    // default build is unchanged.
+    #[cfg(all(feature = "std", not(any(feature = "stable-backtrace", feature = "color"))))]
+    backtrace: Backtrace,
+    #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+    backtrace: Option<backtrace::Backtrace>,
+    help: Vec<HelpEntry>,
+}
+
+impl DefaultHandler {
+    fn capture() -> Self {
+        Self {
+            span_trace: SpanTrace::capture(),
+            #[cfg(all(feature = "std", not(any(feature = "stable-backtrace", feature = "color"))))]
+            backtrace: Backtrace::capture(),
+            #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+            backtrace: capture_backtrace(),
+            help: Vec::new(),
+        }
+    }
+
+    /// The single rendering routine driven by both the plain and the colorized
+    /// paths, so the two can never drift apart. A [`style::Theme`] supplies the
+    /// (possibly empty) ANSI escapes for each part of the report.
+    fn fmt_report(&self, error: &(dyn StdError + 'static), f: &mut Formatter<'_>, theme: &style::Theme) -> core::fmt::Result {
+        let (on, off) = theme.error;
+        write!(f, "{on}{error}{off}")?;
+        for entry in &self.help {
+            if let HelpEntry::Suggestion(message) = entry {
+                let (on, off) = theme.suggestion;
+                write!(f, "\n\n{on}Suggestion{off}: {message}")?;
+            }
+        }
+        for entry in &self.help {
+            if let HelpEntry::Note(message) = entry {
+                let (on, off) = theme.note;
+                write!(f, "\n\n{on}Note{off}: {message}")?;
+            }
+        }
+        for entry in &self.help {
+            if let HelpEntry::Warning(message) = entry {
+                let (on, off) = theme.warning;
+                write!(f, "\n\n{on}Warning{off}: {message}")?;
+            }
+        }
+        let (on, off) = theme.header;
+        write!(f, "\n\n{on}Call history (recent first):{off}\n")?;
+        #[cfg(feature = "color")]
+        if theme.color {
+            write!(f, "{}", color_spantrace::colorize(&self.span_trace))?;
+        } else {
+            Display::fmt(&self.span_trace, f)?;
+        }
+        #[cfg(not(feature = "color"))]
+        Display::fmt(&self.span_trace, f)?;
+        #[cfg(all(feature = "std", not(any(feature = "stable-backtrace", feature = "color"))))]
+        if let BacktraceStatus::Captured = self.backtrace.status() {
+            let (on, off) = theme.header;
+            write!(f, "\n\n{on}Backtrace:{off}\n")?;
+            Display::fmt(&self.backtrace, f)?;
+        }
+        #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+        if let Some(backtrace) = &self.backtrace {
+            let (on, off) = theme.header;
+            write!(f, "\n\n{on}Backtrace:{off}\n")?;
+            fmt_backtrace(backtrace, f, theme)?;
+        }
+        Ok(())
+    }
+}
+
+impl ReportHandler for DefaultHandler {
+    fn debug(&self, error: &(dyn StdError + 'static), f: &mut Formatter<'_>) -> core::fmt::Result {
+        if f.alternate() {
+            return Debug::fmt(error, f);
+        }
+        self.fmt_report(error, f, &style::theme())
+    }
+
+    fn help_mut(&mut self) -> Option<&mut Vec<HelpEntry>> {
+        Some(&mut self.help)
+    }
+}
+
+/// Capture a resolved backtrace via the `backtrace` crate, honouring
+/// `RUST_LIB_BACKTRACE` (preferred) then `RUST_BACKTRACE`. Returns `None` when
+/// neither is enabled, mirroring `std::backtrace::Backtrace::capture`.
+#[cfg(any(feature = "stable-backtrace", feature = "color"))]
+fn capture_backtrace() -> Option<backtrace::Backtrace> {
+    let enabled = match std::env::var_os("RUST_LIB_BACKTRACE") {
+        Some(value) => value != "0",
+        None => match std::env::var_os("RUST_BACKTRACE") {
+            Some(value) => value != "0",
+            None => false,
+        },
+    };
+    enabled.then(backtrace::Backtrace::new)
+}
+
+/// Render a `backtrace` crate backtrace frame-by-frame, dimming runtime/std
+/// frames and highlighting user frames when the theme is colorized.
+#[cfg(any(feature = "stable-backtrace", feature = "color"))]
+fn fmt_backtrace(backtrace: &backtrace::Backtrace, f: &mut Formatter<'_>, theme: &style::Theme) -> core::fmt::Result {
+    // A frame can resolve to more than one symbol when it was inlined, so
+    // number printed lines with their own counter instead of the frame index
+    // - otherwise every symbol from one frame repeats the same number.
+    let mut index = 0;
+    for frame in backtrace.frames() {
+        for symbol in frame.symbols() {
+            let name = match symbol.name() {
+                Some(name) => alloc::format!("{name}"),
+                None => alloc::string::String::from("<unknown>"),
+            };
+            let (on, off) = if is_runtime_frame(&name) { theme.dimmed } else { theme.frame };
+            writeln!(f, "{on}{index:>4}: {name}{off}")?;
+            if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+                let (on, off) = theme.location;
+                writeln!(f, "            {on}at {}:{line}{off}", file.display())?;
+            }
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Heuristic for telling std/runtime frames (dimmed) from user frames
+/// (highlighted), matching color-backtrace's presentation.
+///
+/// This also dims the crate's own capture call chain (`Error::new`,
+/// `make_handler`, `DefaultHandler::capture`, `capture_backtrace`, ...) - it's
+/// bookkeeping that runs on every report, not the call site the user actually
+/// cares about.
+#[cfg(any(feature = "stable-backtrace", feature = "color"))]
+pub(crate) fn is_runtime_frame(name: &str) -> bool {
+    const RUNTIME_PREFIXES: &[&str] = &[
+        "std::",
+        "core::",
+        "alloc::",
+        "backtrace::",
+        "tracing",
+        "tokio::",
+        "<core::",
+        "<std::",
+        "<alloc::",
+        "helpful::",
+        "<helpful::",
+        "__rust",
+        "rust_begin_unwind",
+        "_start",
+    ];
+    // Matched exactly (or as `main::<closure>`), not by prefix, so a user
+    // function like `maintenance_check` isn't swept up too.
+    name == "main" || name.starts_with("main::") || RUNTIME_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(feature = "std")]
+type Hook = Box<dyn Fn(&(dyn StdError + 'static)) -> Box<dyn ReportHandler> + Send + Sync>;
+
+#[cfg(feature = "std")]
+static HOOK: std::sync::OnceLock<Hook> = std::sync::OnceLock::new();
+
+/// Install the global hook used to build a [`ReportHandler`] for every
+/// [`Error`](crate::Error).
+///
+/// Only the first call installs a hook; a later call returns the rejected
+/// hook unchanged. The hook applies to errors constructed after it is
+/// installed; errors constructed earlier keep the [`DefaultHandler`].
+#[cfg(feature = "std")]
+pub fn set_hook(hook: Hook) -> Result<(), Hook> {
+    HOOK.set(hook)
+}
+
+/// Build the handler for a freshly constructed error, consulting the installed
+/// hook and falling back to [`DefaultHandler`].
+pub(crate) fn make_handler(error: &(dyn StdError + 'static)) -> Box<dyn ReportHandler> {
+    #[cfg(feature = "std")]
+    if let Some(hook) = HOOK.get() {
+        return hook(error);
+    }
+    let _ = error;
+    Box::new(DefaultHandler::capture())
+}
+
+/// Styling for the [`DefaultHandler`] report, honouring the `color` feature.
+mod style {
+    /// ANSI `(prefix, suffix)` pairs for each styled part of the report. The
+    /// plain theme uses empty strings, so the same render routine produces
+    /// uncolored output.
+    pub(super) struct Theme {
+        pub error: (&'static str, &'static str),
+        pub header: (&'static str, &'static str),
+        pub note: (&'static str, &'static str),
+        pub warning: (&'static str, &'static str),
+        pub suggestion: (&'static str, &'static str),
+        #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+        pub frame: (&'static str, &'static str),
+        #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+        pub location: (&'static str, &'static str),
+        #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+        pub dimmed: (&'static str, &'static str),
+        #[cfg(feature = "color")]
+        pub color: bool,
+    }
+
+    const PLAIN: Theme = Theme {
+        error: ("", ""),
+        header: ("", ""),
+        note: ("", ""),
+        warning: ("", ""),
+        suggestion: ("", ""),
+        #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+        frame: ("", ""),
+        #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+        location: ("", ""),
+        #[cfg(any(feature = "stable-backtrace", feature = "color"))]
+        dimmed: ("", ""),
+        #[cfg(feature = "color")]
+        color: false,
+    };
+
+    #[cfg(feature = "color")]
+    const RESET: &str = "\u{1b}[0m";
+
+    #[cfg(feature = "color")]
+    const COLORED: Theme = Theme {
+        error: ("\u{1b}[1;31m", RESET),    // bold red
+        header: ("\u{1b}[1m", RESET),      // bold
+        note: ("\u{1b}[36m", RESET),       // cyan
+        warning: ("\u{1b}[33m", RESET),    // yellow
+        suggestion: ("\u{1b}[32m", RESET), // green
+        frame: ("\u{1b}[1m", RESET),       // bold: user code
+        location: ("\u{1b}[2m", RESET),    // dimmed
+        dimmed: ("\u{1b}[2m", RESET),      // dimmed: std/runtime frames
+        color: true,
+    };
+
+    /// Pick the theme for this process: colorized only when the `color` feature
+    /// is on and the environment opts into it.
+    pub(super) fn theme() -> Theme {
+        #[cfg(all(feature = "std", feature = "color"))]
+        if should_colorize() {
+            return COLORED;
+        }
+        PLAIN
+    }
+
+    /// Honour `NO_COLOR`/`CLICOLOR` and fall back to TTY detection, matching the
+    /// color-eyre presentation defaults.
+    #[cfg(all(feature = "std", feature = "color"))]
+    fn should_colorize() -> bool {
+        use std::io::IsTerminal;
+        if std::env::var_os("NO_COLOR").is_some() {
+            return false;
+        }
+        if std::env::var("CLICOLOR").as_deref() == Ok("0") {
+            return false;
+        }
+        std::io::stderr().is_terminal()
+    }
+}
@@ -0,0 +1,100 @@
+use alloc::boxed::Box;
+use core::fmt::Display;
+
+use crate::{Error, HelpEntry};
+
+/// An extension trait for enriching an error with actionable help text.
+///
+/// Modelled on color-eyre's `Section` trait, this lets library authors turn a
+/// raw `No such file or directory` into an error that also tells the user how
+/// to recover. The annotations are rendered after the source error and before
+/// the call-history span trace.
+///
+/// # Caveat: custom report handlers
+///
+/// Help text is stored on the error's [`ReportHandler`](crate::ReportHandler),
+/// so it only survives if that handler keeps a store and returns it from
+/// [`ReportHandler::help_mut`](crate::ReportHandler::help_mut). The built-in
+/// [`DefaultHandler`](crate::DefaultHandler) does; the default `help_mut`
+/// implementation returns `None`, so a hook installed via
+/// [`set_hook`](crate::set_hook) that doesn't override it will **silently
+/// discard** every note, warning, and suggestion attached here. Override
+/// `help_mut` in any custom handler that should carry help text.
+pub trait Section {
+    type Output;
+
+    /// Attach an informational note.
+    fn note<D>(self, note: D) -> Self::Output
+    where
+        D: Display + Send + Sync + 'static;
+
+    /// Attach a warning.
+    fn warning<D>(self, warning: D) -> Self::Output
+    where
+        D: Display + Send + Sync + 'static;
+
+    /// Attach a suggestion for how to resolve the error.
+    fn suggestion<D>(self, suggestion: D) -> Self::Output
+    where
+        D: Display + Send + Sync + 'static;
+}
+
+impl Section for Error {
+    type Output = Error;
+
+    fn note<D>(self, note: D) -> Self::Output
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.with_help(HelpEntry::Note(Box::new(note)))
+    }
+
+    fn warning<D>(self, warning: D) -> Self::Output
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.with_help(HelpEntry::Warning(Box::new(warning)))
+    }
+
+    fn suggestion<D>(self, suggestion: D) -> Self::Output
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.with_help(HelpEntry::Suggestion(Box::new(suggestion)))
+    }
+}
+
+impl<T> Section for crate::Result<T> {
+    type Output = crate::Result<T>;
+
+    fn note<D>(self, note: D) -> Self::Output
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.map_err(|error| error.note(note))
+    }
+
+    fn warning<D>(self, warning: D) -> Self::Output
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.map_err(|error| error.warning(warning))
+    }
+
+    fn suggestion<D>(self, suggestion: D) -> Self::Output
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.map_err(|error| error.suggestion(suggestion))
+    }
+}
+
+impl Error {
+    /// Append a help annotation, forwarding it to the report handler.
+    fn with_help(mut self, entry: HelpEntry) -> Self {
+        if let Some(help) = self.handler.help_mut() {
+            help.push(entry);
+        }
+        self
+    }
+}
@@ -0,0 +1,30 @@
+use core::fmt::{Debug, Display, Formatter};
+
+#[cfg(not(feature = "std"))]
+use crate::StdError;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+/// Wraps a bare message value so it can be stored as the boxed source of an
+/// [`Error`](crate::Error).
+///
+/// `#[repr(transparent)]` so that `Error` can record the `TypeId` of `M` at
+/// construction and later recover the original message value via
+/// `downcast::<M>()` — the wrapper has the exact layout of `M`, so the cast is
+/// sound.
+#[repr(transparent)]
+pub(crate) struct MessageError<M>(pub(crate) M);
+
+impl<M: Display> Display for MessageError<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl<M: Debug> Debug for MessageError<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl<M: Display + Debug + Send + Sync + 'static> StdError for MessageError<M> {}
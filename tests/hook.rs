@@ -0,0 +1,31 @@
+//! `set_hook` installs a process-wide `OnceLock`, so it's exercised in its own
+//! test binary to avoid leaking a custom handler into the unit tests in
+//! `src/lib.rs`.
+
+#![cfg(feature = "std")]
+
+use std::error::Error as StdError;
+use std::fmt::{self, Formatter};
+
+use helpful::{set_hook, Error, ReportHandler};
+
+struct MinimalHandler;
+
+impl ReportHandler for MinimalHandler {
+    fn debug(&self, error: &(dyn StdError + 'static), f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "minimal debug: {error}")
+    }
+
+    fn display(&self, error: &(dyn StdError + 'static), f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "minimal display: {error}")
+    }
+}
+
+#[test]
+fn custom_handler_drives_debug_and_display() {
+    set_hook(Box::new(|_error| Box::new(MinimalHandler) as Box<dyn ReportHandler>)).ok();
+
+    let error = Error::msg("boom");
+    assert_eq!(format!("{error:?}"), "minimal debug: boom");
+    assert_eq!(format!("{error:#}"), "minimal display: boom");
+}